@@ -50,7 +50,7 @@ impl PrimType {
         match self {
             PrimType::Int(width, sign) => width.sizeof(),
             PrimType::Flt(float) => float.sizeof(),
-            PrimType::Bits(num_bits) => power_of_2_greater_than(*num_bits as u64),
+            PrimType::Bits(num_bits) => align_to(*num_bits as u64, 8) / 8,
         }
     }
 
@@ -62,6 +62,18 @@ impl PrimType {
     }
 }
 
+/// The storage unit, in bits, that a `w`-bit bit field is packed into: the
+/// smallest power-of-two number of bits, at least a byte, that can hold it.
+/// A bit field run is never allowed to straddle one of these unit boundaries.
+fn bit_field_unit_bits(w: u64) -> u64 {
+    let mut unit = 8;
+    while unit < w {
+        unit *= 2;
+    }
+    unit
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Endianness {
     Little,
     Big,
@@ -73,9 +85,233 @@ pub struct PrimField {
     endianness: Endianness,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    InvalidDataLayout(String),
+    ShortBuffer { needed_bytes: u64, available_bytes: u64 },
+    TypeMismatch(String),
+    /// A bit field wider than 64 bits, which `PrimData` has no variant to hold.
+    UnsupportedBitWidth { width: u64 },
+}
+
+/// The ABI and preferred alignment for a particular bit width, both given in bits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AlignSpec {
+    pub abi_bits: u64,
+    pub pref_bits: u64,
+}
+
+impl AlignSpec {
+    pub fn abi_bytes(&self) -> u64 {
+        align_to(self.abi_bits, 8) / 8
+    }
+}
+
+/// A parsed LLVM-style data layout string, giving the ABI alignment rules for a
+/// specific target. Drives `Section::sizeof`/`size_bits` so that layout computation
+/// reflects the target instead of assuming natural alignment everywhere.
+pub struct TargetDataLayout {
+    pub endianness: Endianness,
+    pub stack_align_bits: Option<u64>,
+    pub aggregate_align: AlignSpec,
+    pub pointer_align: HashMap<u64, (u64, AlignSpec)>,
+    pub integer_align: HashMap<u64, AlignSpec>,
+    pub float_align: HashMap<u64, AlignSpec>,
+    pub native_widths: Vec<u64>,
+}
+
+impl Default for TargetDataLayout {
+    fn default() -> Self {
+        let mut integer_align = HashMap::new();
+        integer_align.insert(1, AlignSpec { abi_bits: 8, pref_bits: 8 });
+        integer_align.insert(8, AlignSpec { abi_bits: 8, pref_bits: 8 });
+        integer_align.insert(16, AlignSpec { abi_bits: 16, pref_bits: 16 });
+        integer_align.insert(32, AlignSpec { abi_bits: 32, pref_bits: 32 });
+        integer_align.insert(64, AlignSpec { abi_bits: 32, pref_bits: 64 });
+
+        let mut float_align = HashMap::new();
+        float_align.insert(32, AlignSpec { abi_bits: 32, pref_bits: 32 });
+        float_align.insert(64, AlignSpec { abi_bits: 64, pref_bits: 64 });
+
+        let mut pointer_align = HashMap::new();
+        pointer_align.insert(0, (64, AlignSpec { abi_bits: 64, pref_bits: 64 }));
+
+        TargetDataLayout {
+            endianness: Endianness::Little,
+            stack_align_bits: None,
+            aggregate_align: AlignSpec { abi_bits: 0, pref_bits: 64 },
+            pointer_align,
+            integer_align,
+            float_align,
+            native_widths: Vec::new(),
+        }
+    }
+}
+
+impl TargetDataLayout {
+    /// Parse an LLVM-style data layout string, e.g. `e-m:e-i64:64-f80:128-n8:16:32:64-S128`.
+    pub fn parse(spec: &str) -> Result<TargetDataLayout, Error> {
+        let mut layout = TargetDataLayout::default();
+
+        for segment in spec.split('-') {
+            if segment.is_empty() {
+                continue;
+            }
+
+            let mut chars = segment.chars();
+            let tag = chars.next().unwrap();
+            let rest = chars.as_str();
+
+            match tag {
+                'e' => layout.endianness = Endianness::Little,
+                'E' => layout.endianness = Endianness::Big,
+
+                'S' => layout.stack_align_bits = Some(parse_layout_u64(segment, rest)?),
+
+                'a' => layout.aggregate_align = parse_align_spec(segment, rest)?,
+
+                'i' | 'f' => {
+                    let (size, align) = parse_sized_align_spec(segment, rest)?;
+                    if tag == 'i' {
+                        layout.integer_align.insert(size, align);
+                    } else {
+                        layout.float_align.insert(size, align);
+                    }
+                }
+
+                'p' => {
+                    let (addrspace_str, remainder) = split_leading_digits(rest);
+                    let addrspace = if addrspace_str.is_empty() {
+                        0
+                    } else {
+                        parse_layout_u64(segment, addrspace_str)?
+                    };
+                    let parts: Vec<&str> = remainder.trim_start_matches(':').split(':').collect();
+                    if parts.len() < 2 {
+                        return Err(Error::InvalidDataLayout(segment.to_string()));
+                    }
+                    let size = parse_layout_u64(segment, parts[0])?;
+                    let abi_bits = parse_layout_u64(segment, parts[1])?;
+                    let pref_bits = if parts.len() > 2 {
+                        parse_layout_u64(segment, parts[2])?
+                    } else {
+                        abi_bits
+                    };
+                    layout.pointer_align.insert(addrspace, (size, AlignSpec { abi_bits, pref_bits }));
+                }
+
+                'n' => {
+                    let mut widths = Vec::new();
+                    for part in rest.split(':') {
+                        widths.push(parse_layout_u64(segment, part)?);
+                    }
+                    layout.native_widths = widths;
+                }
+
+                // Other segments (mangling, vector alignment, etc.) don't affect
+                // scalar/aggregate layout computation here, so they're ignored.
+                _ => {}
+            }
+        }
+
+        Ok(layout)
+    }
+
+    /// The ABI alignment, in bytes, to use for a primitive of the given type.
+    /// Widths the layout string doesn't mention keep the entries of
+    /// `TargetDataLayout::default()` (e.g. i64 is 32-bit aligned there); the
+    /// type's natural (size-equals-alignment) alignment is used only for widths
+    /// in neither.
+    pub fn align_of(&self, typ: &PrimType) -> u64 {
+        let natural = typ.sizeof();
+        let table = match typ {
+            PrimType::Int(_, _) => &self.integer_align,
+            PrimType::Flt(_) => &self.float_align,
+            // A standalone bit field (e.g. as the first member of a struct) is
+            // aligned to the storage unit that holds it, not ABI-aligned.
+            PrimType::Bits(num_bits) => return bit_field_unit_bits(*num_bits as u64) / 8,
+        };
+
+        match table.get(&typ.size_bits()) {
+            Some(spec) => spec.abi_bytes(),
+            None => natural,
+        }
+    }
+
+    /// The ABI alignment, in bytes, applied to `Struct`/`Union` aggregates.
+    /// A zero `abi_bits` means the layout string has no opinion, so the caller's
+    /// natural alignment should be used instead.
+    pub fn aggregate_align_bytes(&self, natural: u64) -> u64 {
+        if self.aggregate_align.abi_bits == 0 {
+            natural
+        } else {
+            self.aggregate_align.abi_bytes()
+        }
+    }
+}
+
+fn split_leading_digits(s: &str) -> (&str, &str) {
+    let end = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    s.split_at(end)
+}
+
+fn parse_layout_u64(segment: &str, s: &str) -> Result<u64, Error> {
+    s.parse::<u64>().map_err(|_| Error::InvalidDataLayout(segment.to_string()))
+}
+
+fn parse_align_spec(segment: &str, rest: &str) -> Result<AlignSpec, Error> {
+    let parts: Vec<&str> = rest.trim_start_matches(':').split(':').collect();
+    if parts.is_empty() || parts[0].is_empty() {
+        return Err(Error::InvalidDataLayout(segment.to_string()));
+    }
+    let abi_bits = parse_layout_u64(segment, parts[0])?;
+    let pref_bits = if parts.len() > 1 {
+        parse_layout_u64(segment, parts[1])?
+    } else {
+        abi_bits
+    };
+    Ok(AlignSpec { abi_bits, pref_bits })
+}
+
+fn parse_sized_align_spec(segment: &str, rest: &str) -> Result<(u64, AlignSpec), Error> {
+    let parts: Vec<&str> = rest.split(':').collect();
+    if parts.len() < 2 {
+        return Err(Error::InvalidDataLayout(segment.to_string()));
+    }
+    let size = parse_layout_u64(segment, parts[0])?;
+    let abi_bits = parse_layout_u64(segment, parts[1])?;
+    let pref_bits = if parts.len() > 2 {
+        parse_layout_u64(segment, parts[2])?
+    } else {
+        abi_bits
+    };
+    Ok((size, AlignSpec { abi_bits, pref_bits }))
+}
+
+#[test]
+fn test_target_data_layout_parse() {
+    let layout = TargetDataLayout::parse("e-m:e-i64:64-f80:128-n8:16:32:64-S128").unwrap();
+    assert_eq!(layout.endianness, Endianness::Little);
+    assert_eq!(layout.integer_align.get(&64).unwrap().abi_bits, 64);
+    assert_eq!(layout.float_align.get(&80).unwrap().abi_bits, 128);
+    assert_eq!(layout.native_widths, vec![8, 16, 32, 64]);
+    assert_eq!(layout.stack_align_bits, Some(128));
+}
+
+#[test]
+fn test_target_data_layout_big_endian_and_aggregate() {
+    let layout = TargetDataLayout::parse("E-a:0:64-p:64:64:64").unwrap();
+    assert_eq!(layout.endianness, Endianness::Big);
+    assert_eq!(layout.aggregate_align.abi_bits, 0);
+    assert_eq!(layout.pointer_align.get(&0).unwrap().0, 64);
+}
+
 pub struct Struct {
     name: String,
     fields: Vec<Section>,
+    /// `repr(packed(n))`'s `n`, in bytes. Must be a power of two, as it is
+    /// only ever used to clamp a field's target-layout alignment (see
+    /// `clamp_to_packing`), which is itself always a power of two.
     packing: Option<u64>,
 }
 
@@ -92,80 +328,337 @@ pub enum Section {
 }
 
 impl Section {
-    pub fn sizeof(&self) -> u64 {
+    /// The alignment, in bytes, to use for this section as a field within an
+    /// enclosing struct, per `layout`.
+    fn align_bytes(&self, layout: &TargetDataLayout) -> u64 {
+        match self {
+            Section::Prim(prim) => layout.align_of(&prim.typ),
+            Section::Struct(structure) => struct_align_bytes(structure, layout),
+            Section::Union(union) => union_align_bytes(union, layout),
+            Section::Array(section, _) => section.align_bytes(layout),
+        }
+    }
+
+    pub fn sizeof(&self, layout: &TargetDataLayout) -> u64 {
         match self {
             Section::Prim(prim) => {
                 return prim.typ.sizeof();
             }
 
             Section::Struct(structure) => {
-                // TODO need to deal with bit fields
-                // likely once hit bits, accumulate without alignment until
-                // aligned again.
-                // also make sure to expand to byte alignment at the end, and
-                // to ensure stride at the end
-                let mut size = 0;
-                if structure.fields.len() > 0 {
-                    let first_size = structure.fields[0].sizeof();
-                    for field in structure.fields.iter() {
-                        let field_size = field.sizeof();
-                        size = align_to(size, field_size) + field_size;
-                    }
-                    // align to first field, to ensure stride remains aligned
-                    size = align_to(size, first_size);
-                 }
-                return size;
+                return struct_size_bits(structure, layout) / 8;
             }
 
             Section::Array(section, num_elems) => {
-                return section.sizeof() * num_elems;
+                return section.sizeof(layout) * num_elems;
             }
 
             Section::Union(union) => {
                 let mut largest = 0;
                 for field in union.fields.iter() {
-                    largest = std::cmp::max(largest, field.sizeof());
+                    largest = std::cmp::max(largest, field.sizeof(layout));
                 }
-                return largest;
+                return align_to(largest, union_align_bytes(union, layout));
             }
         }
     }
 
-    pub fn size_bits(&self) -> u64 {
+    pub fn size_bits(&self, layout: &TargetDataLayout) -> u64 {
         match self {
             Section::Prim(prim) => {
                 return prim.typ.size_bits();
             }
 
             Section::Struct(structure) => {
-                // TODO need to deal with bit fields
-                // they should be packed, and cause next fields to be
-                // packed as bit fields, until byte alignment
-                let mut size = 0;
-                if structure.fields.len() > 0 {
-                    let first_size = structure.fields[0].sizeof();
-                    for field in structure.fields.iter() {
-                        let field_size = field.sizeof();
-                        size = align_to(size, field_size) + field_size;
-                    }
-                    // align to first field, to ensure stride remains aligned
-                    size = align_to(size, first_size);
-                 }
-                return size;
+                return struct_size_bits(structure, layout);
             }
 
             Section::Array(section, num_elems) => {
-                return section.size_bits() * num_elems;
+                return section.size_bits(layout) * num_elems;
             }
 
             Section::Union(union) => {
                 let mut largest = 0;
                 for field in union.fields.iter() {
-                    largest = std::cmp::max(largest, field.size_bits());
+                    largest = std::cmp::max(largest, field.size_bits(layout));
+                }
+                return align_to(largest, union_align_bytes(union, layout) * 8);
+            }
+        }
+    }
+
+    /// The name this section is known by to its enclosing container: a
+    /// `Prim`'s field name, or a `Struct`/`Union`'s type name.
+    fn report_name(&self) -> String {
+        match self {
+            Section::Prim(prim) => prim.name.clone(),
+            Section::Struct(structure) => structure.name.clone(),
+            Section::Union(union) => union.name.clone(),
+            Section::Array(section, _) => section.report_name(),
+        }
+    }
+
+    /// Walk this section and report, for every leaf and aggregate, its
+    /// computed offset, size, alignment, and any padding inserted before it
+    /// to satisfy alignment — analogous to rustc's `-Z print-type-sizes`.
+    pub fn layout_report(&self, layout: &TargetDataLayout) -> LayoutReport {
+        self.layout_report_at(&self.report_name(), 0, 0, layout)
+    }
+
+    fn layout_report_at(&self, name: &str, offset_bits: u64, padding_bits: u64, layout: &TargetDataLayout) -> LayoutReport {
+        match self {
+            Section::Prim(_) => LayoutReport {
+                name: name.to_string(),
+                offset_bits,
+                size_bits: self.size_bits(layout),
+                align_bytes: Some(self.align_bytes(layout)),
+                padding_bits,
+                count: None,
+                children: Vec::new(),
+            },
+
+            Section::Struct(structure) => {
+                let mut children = Vec::new();
+                let mut bit_pos: u64 = 0;
+
+                for field in structure.fields.iter() {
+                    if let Section::Prim(prim) = field {
+                        if let PrimType::Bits(num_bits) = prim.typ {
+                            let width = num_bits as u64;
+                            let start = if width == 0 {
+                                align_to(bit_pos, 8)
+                            } else {
+                                let unit_bits = bit_field_unit_bits(width);
+                                let unit_start = (bit_pos / unit_bits) * unit_bits;
+                                if bit_pos + width > unit_start + unit_bits {
+                                    align_to(bit_pos, unit_bits)
+                                } else {
+                                    bit_pos
+                                }
+                            };
+                            children.push(LayoutReport {
+                                name: prim.name.clone(),
+                                offset_bits: start,
+                                size_bits: width,
+                                align_bytes: None,
+                                padding_bits: start - bit_pos,
+                                count: None,
+                                children: Vec::new(),
+                            });
+                            bit_pos = start + width;
+                            continue;
+                        }
+                    }
+
+                    let field_align = clamp_to_packing(field.align_bytes(layout), structure.packing);
+                    let byte_start = align_to(align_to(bit_pos, 8) / 8, field_align);
+                    let field_offset_bits = byte_start * 8;
+                    let field_padding_bits = field_offset_bits - bit_pos;
+                    children.push(field.layout_report_at(&field.report_name(), field_offset_bits, field_padding_bits, layout));
+                    bit_pos = field_offset_bits + field.size_bits(layout);
+                }
+
+                let total_bits = struct_size_bits(structure, layout);
+                let trailing_padding = total_bits - align_to(bit_pos, 8);
+
+                LayoutReport {
+                    name: name.to_string(),
+                    offset_bits,
+                    size_bits: total_bits,
+                    align_bytes: Some(self.align_bytes(layout)),
+                    padding_bits,
+                    count: None,
+                    children: if trailing_padding > 0 {
+                        let mut children = children;
+                        children.push(LayoutReport {
+                            name: "<padding>".to_string(),
+                            offset_bits: align_to(bit_pos, 8),
+                            size_bits: trailing_padding,
+                            align_bytes: None,
+                            padding_bits: 0,
+                            count: None,
+                            children: Vec::new(),
+                        });
+                        children
+                    } else {
+                        children
+                    },
+                }
+            }
+
+            Section::Union(union) => {
+                let children = union
+                    .fields
+                    .iter()
+                    .map(|field| field.layout_report_at(&field.report_name(), 0, 0, layout))
+                    .collect();
+
+                LayoutReport {
+                    name: name.to_string(),
+                    offset_bits,
+                    size_bits: self.size_bits(layout),
+                    align_bytes: Some(self.align_bytes(layout)),
+                    padding_bits,
+                    count: None,
+                    children,
+                }
+            }
+
+            Section::Array(section, num_elems) => {
+                let element = section.layout_report_at(&section.report_name(), 0, 0, layout);
+
+                LayoutReport {
+                    name: name.to_string(),
+                    offset_bits,
+                    size_bits: self.size_bits(layout),
+                    align_bytes: Some(self.align_bytes(layout)),
+                    padding_bits,
+                    count: Some(*num_elems),
+                    children: vec![element],
+                }
+            }
+        }
+    }
+}
+
+/// A report of the layout of a `Section`: its computed offset, size,
+/// alignment, and any padding inserted before it, mirroring rustc's
+/// `print-type-size` output. `count` is set for `Array` nodes, whose single
+/// child describes one element's layout.
+pub struct LayoutReport {
+    pub name: String,
+    pub offset_bits: u64,
+    pub size_bits: u64,
+    pub align_bytes: Option<u64>,
+    pub padding_bits: u64,
+    pub count: Option<u64>,
+    pub children: Vec<LayoutReport>,
+}
+
+/// Walk a `Struct`'s fields, in declaration order, with a bit cursor.
+fn struct_size_bits(structure: &Struct, layout: &TargetDataLayout) -> u64 {
+    let order: Vec<usize> = (0..structure.fields.len()).collect();
+    struct_size_bits_ordered(structure, &order, layout)
+}
+
+/// Walk a `Struct`'s fields in the given order (a permutation of field
+/// indices) with a bit cursor, packing consecutive `PrimType::Bits` fields
+/// instead of rounding each one up to a whole field. Non-bit fields (and the
+/// end of the struct) close out the current bit run by rounding the cursor
+/// up to the next byte boundary first.
+fn struct_size_bits_ordered(structure: &Struct, order: &[usize], layout: &TargetDataLayout) -> u64 {
+    if order.is_empty() {
+        return 0;
+    }
+
+    let struct_align_bits = struct_align_bytes(structure, layout) * 8;
+
+    let mut bit_pos: u64 = 0;
+    for &idx in order {
+        let field = &structure.fields[idx];
+
+        if let Section::Prim(prim) = field {
+            if let PrimType::Bits(num_bits) = prim.typ {
+                let width = num_bits as u64;
+                if width == 0 {
+                    // Zero-width bit field: force alignment to the next unit
+                    // boundary, occupying no storage itself.
+                    bit_pos = align_to(bit_pos, 8);
+                } else {
+                    let unit_bits = bit_field_unit_bits(width);
+                    let unit_start = (bit_pos / unit_bits) * unit_bits;
+                    if bit_pos + width > unit_start + unit_bits {
+                        // Placing this field here would straddle the storage
+                        // unit boundary, so start a fresh unit instead.
+                        bit_pos = align_to(bit_pos, unit_bits);
+                    }
+                    bit_pos += width;
                 }
-                return largest;
+                continue;
             }
         }
+
+        // Non-bit field: close out any in-progress bit run first.
+        bit_pos = align_to(bit_pos, 8);
+        let byte_pos = bit_pos / 8;
+        let field_align = clamp_to_packing(field.align_bytes(layout), structure.packing);
+        let field_size = field.sizeof(layout);
+        bit_pos = (align_to(byte_pos, field_align) + field_size) * 8;
+    }
+
+    // The struct may end mid bit-run; round out to a whole byte before
+    // applying the trailing stride alignment.
+    bit_pos = align_to(bit_pos, 8);
+    align_to(bit_pos, struct_align_bits)
+}
+
+/// The alignment, in bytes, of a `Struct` as a whole: the maximum of its
+/// fields' alignments (clamped to `packing`, if any), further widened by the
+/// target's aggregate alignment unless the struct is packed — packing
+/// explicitly overrides the target's aggregate rule.
+fn struct_align_bytes(structure: &Struct, layout: &TargetDataLayout) -> u64 {
+    let max_field_align = structure
+        .fields
+        .iter()
+        .map(|field| field.align_bytes(layout))
+        .max()
+        .unwrap_or(1);
+    let clamped = clamp_to_packing(max_field_align, structure.packing);
+
+    if structure.packing.is_some() {
+        clamped
+    } else {
+        std::cmp::max(clamped, layout.aggregate_align_bytes(clamped))
+    }
+}
+
+/// The alignment, in bytes, of a `Union` as a whole: the maximum of its
+/// variants' alignments, widened by the target's aggregate alignment.
+fn union_align_bytes(union: &Union, layout: &TargetDataLayout) -> u64 {
+    let max_field_align = union
+        .fields
+        .iter()
+        .map(|field| field.align_bytes(layout))
+        .max()
+        .unwrap_or(1);
+    std::cmp::max(max_field_align, layout.aggregate_align_bytes(max_field_align))
+}
+
+impl Struct {
+    /// The permutation of field indices, sorted by descending alignment
+    /// (ties broken by original index for stability), that minimizes padding
+    /// when laying out this struct — mirroring Rust's default (non-`repr(C)`)
+    /// field ordering. Packed structs are left in declaration order, even
+    /// where a `packed(n)` with `n > 1` still has padding. Structs with
+    /// any bit field are also left in declaration order: which bits share a
+    /// storage unit depends on declaration adjacency, so sorting by
+    /// pseudo-alignment could merge or split previously-unrelated bit-field
+    /// runs into a layout with no real-world meaning.
+    pub fn optimized_order(&self, layout: &TargetDataLayout) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.fields.len()).collect();
+        let has_bit_field = self
+            .fields
+            .iter()
+            .any(|field| matches!(field, Section::Prim(prim) if matches!(prim.typ, PrimType::Bits(_))));
+        if self.packing.is_some() || has_bit_field {
+            return order;
+        }
+
+        order.sort_by(|&a, &b| {
+            let align_a = self.fields[a].align_bytes(layout);
+            let align_b = self.fields[b].align_bytes(layout);
+            align_b.cmp(&align_a).then(a.cmp(&b))
+        });
+        order
+    }
+
+    /// The size of this struct if its fields were laid out via
+    /// `optimized_order` instead of declaration order, along with the
+    /// permutation used.
+    pub fn sizeof_optimized(&self, layout: &TargetDataLayout) -> (u64, Vec<usize>) {
+        let order = self.optimized_order(layout);
+        let size = struct_size_bits_ordered(self, &order, layout) / 8;
+        (size, order)
     }
 }
 
@@ -175,6 +668,177 @@ pub struct Field {
     typ: Section,
 }
 
+#[cfg(test)]
+fn test_prim_int(name: &str, width: IntWidth) -> Section {
+    Section::Prim(PrimField {
+        name: name.to_string(),
+        typ: PrimType::Int(width, Sign::Unsiged),
+        endianness: Endianness::Little,
+    })
+}
+
+#[test]
+fn test_packed_struct_removes_padding() {
+    let layout = TargetDataLayout::default();
+    let structure = Struct {
+        name: "S".to_string(),
+        fields: vec![
+            test_prim_int("a", IntWidth::Int8),
+            test_prim_int("b", IntWidth::Int32),
+        ],
+        packing: Some(1),
+    };
+    let section = Section::Struct(Box::new(structure));
+    // packed(1): no inter-field padding, no trailing stride padding.
+    assert_eq!(5, section.sizeof(&layout));
+}
+
+#[test]
+fn test_unpacked_struct_keeps_natural_padding() {
+    let layout = TargetDataLayout::default();
+    let structure = Struct {
+        name: "S".to_string(),
+        fields: vec![
+            test_prim_int("a", IntWidth::Int8),
+            test_prim_int("b", IntWidth::Int32),
+        ],
+        packing: None,
+    };
+    let section = Section::Struct(Box::new(structure));
+    assert_eq!(8, section.sizeof(&layout));
+}
+
+#[test]
+fn test_packed_struct_contributes_reduced_alignment_to_outer() {
+    let layout = TargetDataLayout::default();
+    let inner = Struct {
+        name: "Inner".to_string(),
+        fields: vec![
+            test_prim_int("a", IntWidth::Int8),
+            test_prim_int("b", IntWidth::Int32),
+        ],
+        packing: Some(1),
+    };
+    let outer = Struct {
+        name: "Outer".to_string(),
+        fields: vec![
+            test_prim_int("x", IntWidth::Int8),
+            Section::Struct(Box::new(inner)),
+        ],
+        packing: None,
+    };
+    let section = Section::Struct(Box::new(outer));
+    // The packed inner struct has alignment 1 and size 5, so it's placed
+    // right after `x` with no padding: 1 (x) + 5 (inner) = 6.
+    assert_eq!(6, section.sizeof(&layout));
+}
+
+#[cfg(test)]
+fn test_bits(name: &str, width: u8) -> Section {
+    Section::Prim(PrimField {
+        name: name.to_string(),
+        typ: PrimType::Bits(width),
+        endianness: Endianness::Little,
+    })
+}
+
+#[test]
+fn test_adjacent_bit_fields_share_a_byte() {
+    let layout = TargetDataLayout::default();
+    let structure = Struct {
+        name: "Flags".to_string(),
+        fields: vec![test_bits("a", 3), test_bits("b", 5)],
+        packing: None,
+    };
+    let section = Section::Struct(Box::new(structure));
+    assert_eq!(8, section.size_bits(&layout));
+    assert_eq!(1, section.sizeof(&layout));
+}
+
+#[test]
+fn test_bit_field_run_does_not_straddle_storage_unit() {
+    let layout = TargetDataLayout::default();
+    // 6 + 6 bits would fit in 12 bits, but the second field can't straddle
+    // the 8-bit storage unit boundary, so it starts a fresh byte.
+    let structure = Struct {
+        name: "S".to_string(),
+        fields: vec![test_bits("a", 6), test_bits("b", 6)],
+        packing: None,
+    };
+    let section = Section::Struct(Box::new(structure));
+    assert_eq!(16, section.size_bits(&layout));
+    assert_eq!(2, section.sizeof(&layout));
+}
+
+#[test]
+fn test_bits_sizeof_is_a_byte_count_not_an_exponent() {
+    let layout = TargetDataLayout::default();
+    assert_eq!(4, PrimType::Bits(32).sizeof());
+    let section = Section::Array(Box::new(test_bits("n", 32)), 5);
+    assert_eq!(20, section.sizeof(&layout));
+}
+
+#[test]
+fn test_zero_width_bit_field_forces_alignment() {
+    let layout = TargetDataLayout::default();
+    let structure = Struct {
+        name: "S".to_string(),
+        fields: vec![test_bits("a", 3), test_bits("pad", 0), test_bits("b", 3)],
+        packing: None,
+    };
+    let section = Section::Struct(Box::new(structure));
+    // The zero-width field forces `b` into a fresh byte after `a`.
+    assert_eq!(16, section.size_bits(&layout));
+}
+
+#[test]
+fn test_bit_field_followed_by_normal_field_byte_aligns() {
+    let layout = TargetDataLayout::default();
+    let structure = Struct {
+        name: "S".to_string(),
+        fields: vec![test_bits("flag", 1), test_prim_int("n", IntWidth::Int32)],
+        packing: None,
+    };
+    let section = Section::Struct(Box::new(structure));
+    // `flag` closes out to a full byte before `n`, which then needs 4-byte
+    // alignment.
+    assert_eq!(8, section.sizeof(&layout));
+}
+
+#[test]
+fn test_layout_report_shows_offsets_and_padding() {
+    let layout = TargetDataLayout::default();
+    let structure = Struct {
+        name: "S".to_string(),
+        fields: vec![
+            test_prim_int("a", IntWidth::Int8),
+            test_prim_int("b", IntWidth::Int32),
+        ],
+        packing: None,
+    };
+    let section = Section::Struct(Box::new(structure));
+    let report = section.layout_report(&layout);
+
+    assert_eq!(64, report.size_bits);
+    assert_eq!(2, report.children.len());
+    assert_eq!("a", report.children[0].name);
+    assert_eq!(0, report.children[0].offset_bits);
+    assert_eq!("b", report.children[1].name);
+    assert_eq!(32, report.children[1].offset_bits);
+    assert_eq!(24, report.children[1].padding_bits);
+}
+
+#[test]
+fn test_layout_report_array_has_single_element_child_and_count() {
+    let layout = TargetDataLayout::default();
+    let array = Section::Array(Box::new(test_prim_int("elem", IntWidth::Int16)), 4);
+    let report = array.layout_report(&layout);
+
+    assert_eq!(Some(4), report.count);
+    assert_eq!(1, report.children.len());
+    assert_eq!(16, report.children[0].size_bits);
+}
+
 pub fn power_of_2_greater_than(num_bits: u64) -> u64 {
     let mut power: u64 = 0;
 
@@ -206,6 +870,27 @@ pub fn align_to(size: u64, align: u64) -> u64 {
     return size + (align - m) * (b as u64);
 }
 
+/// `repr(packed(n))`: clamp a field's alignment to the struct's `packing`
+/// value, if any. `packing` must be a power of two.
+fn clamp_to_packing(align: u64, packing: Option<u64>) -> u64 {
+    match packing {
+        Some(n) => {
+            // A real `assert!`, not `debug_assert!`: packing is part of the
+            // layout's external contract, so a non-power-of-two value must be
+            // rejected in release builds too, not just silently mis-clamped.
+            assert!(n.is_power_of_two(), "packing must be a power of two, got {n}");
+            std::cmp::min(align, n)
+        }
+        None => align,
+    }
+}
+
+#[test]
+#[should_panic(expected = "power of two")]
+fn test_clamp_to_packing_rejects_non_power_of_two() {
+    clamp_to_packing(8, Some(3));
+}
+
 #[test]
 pub fn test_align_to() {
     assert_eq!(8, align_to(5, 4));
@@ -214,6 +899,7 @@ pub fn test_align_to() {
     assert_eq!(10, align_to(9, 2));
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum PrimData {
     U8(u8),
     U16(u16),
@@ -226,3 +912,481 @@ pub enum PrimData {
     F32(f32),
     F64(f64),
 }
+
+/// A decoded tree mirroring a `Section`: a map of named fields for `Struct`,
+/// the raw overlapping bytes for a `Union` (since which variant is "active"
+/// isn't known from the bytes alone), a `Vec` for `Array`, and a `PrimData`
+/// leaf for `Prim`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Prim(PrimData),
+    Struct(HashMap<String, Value>),
+    Union(Vec<u8>),
+    Array(Vec<Value>),
+}
+
+impl Section {
+    /// Decode `bytes` against this section's layout under `layout`.
+    pub fn read(&self, bytes: &[u8], layout: &TargetDataLayout) -> Result<Value, Error> {
+        let (value, _) = self.read_at(bytes, 0, layout)?;
+        Ok(value)
+    }
+
+    /// Encode `value` against this section's layout under `layout`, returning
+    /// a buffer exactly `self.sizeof(layout)` bytes long.
+    pub fn write(&self, value: &Value, layout: &TargetDataLayout) -> Result<Vec<u8>, Error> {
+        let mut bytes = vec![0u8; self.sizeof(layout) as usize];
+        self.write_at(value, &mut bytes, 0, layout)?;
+        Ok(bytes)
+    }
+
+    fn read_at(&self, bytes: &[u8], bit_pos: u64, layout: &TargetDataLayout) -> Result<(Value, u64), Error> {
+        match self {
+            Section::Prim(prim) => {
+                let width = prim.typ.size_bits();
+                require_bits(bytes, bit_pos, width)?;
+                let data = if let PrimType::Bits(num_bits) = prim.typ {
+                    check_bit_width(num_bits as u64)?;
+                    read_bits(bytes, bit_pos, num_bits as u64)
+                } else {
+                    read_prim(bytes, (bit_pos / 8) as usize, &prim.typ, prim.endianness)
+                };
+                Ok((Value::Prim(data), bit_pos + width))
+            }
+
+            Section::Struct(structure) => {
+                let mut fields = HashMap::new();
+                let mut cursor = bit_pos;
+
+                for field in structure.fields.iter() {
+                    if let Section::Prim(prim) = field {
+                        if let PrimType::Bits(num_bits) = prim.typ {
+                            let width = num_bits as u64;
+                            check_bit_width(width)?;
+                            let start = bit_field_start(cursor - bit_pos, width) + bit_pos;
+                            require_bits(bytes, start, width)?;
+                            let data = read_bits(bytes, start, width);
+                            fields.insert(prim.name.clone(), Value::Prim(data));
+                            cursor = start + width;
+                            continue;
+                        }
+                    }
+
+                    let field_align = clamp_to_packing(field.align_bytes(layout), structure.packing);
+                    let byte_start = align_to(align_to(cursor - bit_pos, 8) / 8, field_align);
+                    let field_bit_pos = bit_pos + byte_start * 8;
+                    let (value, next) = field.read_at(bytes, field_bit_pos, layout)?;
+                    fields.insert(field.report_name(), value);
+                    cursor = next;
+                }
+
+                Ok((Value::Struct(fields), bit_pos + struct_size_bits(structure, layout)))
+            }
+
+            Section::Union(union) => {
+                let size_bits = self.size_bits(layout);
+                require_bits(bytes, bit_pos, size_bits)?;
+                let byte_start = (bit_pos / 8) as usize;
+                let byte_len = (size_bits / 8) as usize;
+                let _ = union;
+                Ok((Value::Union(bytes[byte_start..byte_start + byte_len].to_vec()), bit_pos + size_bits))
+            }
+
+            Section::Array(section, num_elems) => {
+                let mut values = Vec::with_capacity(*num_elems as usize);
+                let mut cursor = bit_pos;
+                for _ in 0..*num_elems {
+                    let (value, next) = section.read_at(bytes, cursor, layout)?;
+                    values.push(value);
+                    cursor = next;
+                }
+                Ok((Value::Array(values), cursor))
+            }
+        }
+    }
+
+    fn write_at(&self, value: &Value, bytes: &mut [u8], bit_pos: u64, layout: &TargetDataLayout) -> Result<u64, Error> {
+        match self {
+            Section::Prim(prim) => {
+                let Value::Prim(data) = value else {
+                    return Err(Error::TypeMismatch(format!("expected a primitive for field `{}`", prim.name)));
+                };
+                let width = prim.typ.size_bits();
+                require_bits(bytes, bit_pos, width)?;
+                if let PrimType::Bits(num_bits) = prim.typ {
+                    check_bit_width(num_bits as u64)?;
+                    write_bits(bytes, bit_pos, num_bits as u64, prim_data_as_u64(*data));
+                } else {
+                    write_prim(bytes, (bit_pos / 8) as usize, *data, prim.endianness);
+                }
+                Ok(bit_pos + width)
+            }
+
+            Section::Struct(structure) => {
+                let Value::Struct(fields) = value else {
+                    return Err(Error::TypeMismatch(format!("expected a struct for `{}`", structure.name)));
+                };
+                let mut cursor = bit_pos;
+
+                for field in structure.fields.iter() {
+                    if let Section::Prim(prim) = field {
+                        if let PrimType::Bits(num_bits) = prim.typ {
+                            let width = num_bits as u64;
+                            check_bit_width(width)?;
+                            let start = bit_field_start(cursor - bit_pos, width) + bit_pos;
+                            require_bits(bytes, start, width)?;
+                            let field_value = fields
+                                .get(&prim.name)
+                                .ok_or_else(|| Error::TypeMismatch(format!("missing field `{}`", prim.name)))?;
+                            let Value::Prim(data) = field_value else {
+                                return Err(Error::TypeMismatch(format!("expected a primitive for field `{}`", prim.name)));
+                            };
+                            write_bits(bytes, start, width, prim_data_as_u64(*data));
+                            cursor = start + width;
+                            continue;
+                        }
+                    }
+
+                    let field_align = clamp_to_packing(field.align_bytes(layout), structure.packing);
+                    let byte_start = align_to(align_to(cursor - bit_pos, 8) / 8, field_align);
+                    let field_bit_pos = bit_pos + byte_start * 8;
+                    let name = field.report_name();
+                    let field_value = fields
+                        .get(&name)
+                        .ok_or_else(|| Error::TypeMismatch(format!("missing field `{}`", name)))?;
+                    cursor = field.write_at(field_value, bytes, field_bit_pos, layout)?;
+                }
+
+                Ok(bit_pos + struct_size_bits(structure, layout))
+            }
+
+            Section::Union(union) => {
+                let Value::Union(raw) = value else {
+                    return Err(Error::TypeMismatch(format!("expected union bytes for `{}`", union.name)));
+                };
+                let size_bits = self.size_bits(layout);
+                require_bits(bytes, bit_pos, size_bits)?;
+                let byte_start = (bit_pos / 8) as usize;
+                let byte_len = (size_bits / 8) as usize;
+                if raw.len() != byte_len {
+                    return Err(Error::TypeMismatch(format!("union `{}` expects {} bytes, got {}", union.name, byte_len, raw.len())));
+                }
+                bytes[byte_start..byte_start + byte_len].copy_from_slice(raw);
+                Ok(bit_pos + size_bits)
+            }
+
+            Section::Array(section, num_elems) => {
+                let Value::Array(values) = value else {
+                    return Err(Error::TypeMismatch("expected an array".to_string()));
+                };
+                if values.len() as u64 != *num_elems {
+                    return Err(Error::TypeMismatch(format!("array expects {} elements, got {}", num_elems, values.len())));
+                }
+                let mut cursor = bit_pos;
+                for element in values.iter() {
+                    cursor = section.write_at(element, bytes, cursor, layout)?;
+                }
+                Ok(cursor)
+            }
+        }
+    }
+}
+
+/// Where, relative to the start of the enclosing struct, a `width`-bit field
+/// lands given the struct's bit cursor `cursor` — mirrors the cursor rules in
+/// `struct_size_bits`.
+fn bit_field_start(cursor: u64, width: u64) -> u64 {
+    if width == 0 {
+        return align_to(cursor, 8);
+    }
+    let unit_bits = bit_field_unit_bits(width);
+    let unit_start = (cursor / unit_bits) * unit_bits;
+    if cursor + width > unit_start + unit_bits {
+        align_to(cursor, unit_bits)
+    } else {
+        cursor
+    }
+}
+
+/// `read_bits`/`write_bits` hold a bit field's value in a `u64` accumulator,
+/// so widths beyond 64 bits can't be represented and must be rejected here
+/// rather than left to overflow the shift in those functions.
+fn check_bit_width(width: u64) -> Result<(), Error> {
+    if width > 64 {
+        Err(Error::UnsupportedBitWidth { width })
+    } else {
+        Ok(())
+    }
+}
+
+fn require_bits(bytes: &[u8], bit_pos: u64, width: u64) -> Result<(), Error> {
+    let needed_bytes = align_to(bit_pos + width, 8) / 8;
+    if needed_bytes > bytes.len() as u64 {
+        Err(Error::ShortBuffer { needed_bytes, available_bytes: bytes.len() as u64 })
+    } else {
+        Ok(())
+    }
+}
+
+fn read_bits(bytes: &[u8], bit_pos: u64, width: u64) -> PrimData {
+    let mut value: u64 = 0;
+    for i in 0..width {
+        let bit_index = bit_pos + i;
+        let byte = bytes[(bit_index / 8) as usize];
+        let bit = (byte >> (bit_index % 8)) & 1;
+        value |= (bit as u64) << i;
+    }
+    match bit_field_unit_bits(width) / 8 {
+        1 => PrimData::U8(value as u8),
+        2 => PrimData::U16(value as u16),
+        4 => PrimData::U32(value as u32),
+        _ => PrimData::U64(value),
+    }
+}
+
+fn write_bits(bytes: &mut [u8], bit_pos: u64, width: u64, value: u64) {
+    for i in 0..width {
+        let bit_index = bit_pos + i;
+        let bit = (value >> i) & 1;
+        let byte_index = (bit_index / 8) as usize;
+        let mask = 1u8 << (bit_index % 8);
+        if bit == 1 {
+            bytes[byte_index] |= mask;
+        } else {
+            bytes[byte_index] &= !mask;
+        }
+    }
+}
+
+fn prim_data_as_u64(data: PrimData) -> u64 {
+    match data {
+        PrimData::U8(v) => v as u64,
+        PrimData::U16(v) => v as u64,
+        PrimData::U32(v) => v as u64,
+        PrimData::U64(v) => v,
+        PrimData::I8(v) => v as u64,
+        PrimData::I16(v) => v as u64,
+        PrimData::I32(v) => v as u64,
+        PrimData::I64(v) => v as u64,
+        PrimData::F32(v) => v.to_bits() as u64,
+        PrimData::F64(v) => v.to_bits(),
+    }
+}
+
+fn read_prim(bytes: &[u8], byte_pos: usize, typ: &PrimType, endianness: Endianness) -> PrimData {
+    macro_rules! read_int {
+        ($ty:ty, $n:expr) => {{
+            let mut buf = [0u8; $n];
+            buf.copy_from_slice(&bytes[byte_pos..byte_pos + $n]);
+            match endianness {
+                Endianness::Little => <$ty>::from_le_bytes(buf),
+                Endianness::Big => <$ty>::from_be_bytes(buf),
+            }
+        }};
+    }
+
+    match typ {
+        PrimType::Int(IntWidth::Int8, Sign::Signed) => PrimData::I8(read_int!(i8, 1)),
+        PrimType::Int(IntWidth::Int8, Sign::Unsiged) => PrimData::U8(read_int!(u8, 1)),
+        PrimType::Int(IntWidth::Int16, Sign::Signed) => PrimData::I16(read_int!(i16, 2)),
+        PrimType::Int(IntWidth::Int16, Sign::Unsiged) => PrimData::U16(read_int!(u16, 2)),
+        PrimType::Int(IntWidth::Int32, Sign::Signed) => PrimData::I32(read_int!(i32, 4)),
+        PrimType::Int(IntWidth::Int32, Sign::Unsiged) => PrimData::U32(read_int!(u32, 4)),
+        PrimType::Int(IntWidth::Int64, Sign::Signed) => PrimData::I64(read_int!(i64, 8)),
+        PrimType::Int(IntWidth::Int64, Sign::Unsiged) => PrimData::U64(read_int!(u64, 8)),
+        PrimType::Flt(Float::Float) => PrimData::F32(f32::from_bits(read_int!(u32, 4))),
+        PrimType::Flt(Float::Double) => PrimData::F64(f64::from_bits(read_int!(u64, 8))),
+        PrimType::Bits(_) => unreachable!("bit fields are decoded via read_bits"),
+    }
+}
+
+fn write_prim(bytes: &mut [u8], byte_pos: usize, data: PrimData, endianness: Endianness) {
+    macro_rules! write_int {
+        ($v:expr, $n:expr) => {{
+            let buf = match endianness {
+                Endianness::Little => $v.to_le_bytes(),
+                Endianness::Big => $v.to_be_bytes(),
+            };
+            bytes[byte_pos..byte_pos + $n].copy_from_slice(&buf);
+        }};
+    }
+
+    match data {
+        PrimData::I8(v) => write_int!(v, 1),
+        PrimData::U8(v) => write_int!(v, 1),
+        PrimData::I16(v) => write_int!(v, 2),
+        PrimData::U16(v) => write_int!(v, 2),
+        PrimData::I32(v) => write_int!(v, 4),
+        PrimData::U32(v) => write_int!(v, 4),
+        PrimData::I64(v) => write_int!(v, 8),
+        PrimData::U64(v) => write_int!(v, 8),
+        PrimData::F32(v) => write_int!(v.to_bits(), 4),
+        PrimData::F64(v) => write_int!(v.to_bits(), 8),
+    }
+}
+
+#[test]
+fn test_read_struct_round_trips_through_write() {
+    let layout = TargetDataLayout::default();
+    let structure = Struct {
+        name: "S".to_string(),
+        fields: vec![
+            test_prim_int("a", IntWidth::Int8),
+            test_prim_int("b", IntWidth::Int32),
+        ],
+        packing: None,
+    };
+    let section = Section::Struct(Box::new(structure));
+    let bytes: Vec<u8> = vec![0x7F, 0x00, 0x00, 0x00, 0x01, 0x02, 0x03, 0x04];
+
+    let value = section.read(&bytes, &layout).unwrap();
+    let Value::Struct(fields) = &value else { panic!("expected a struct") };
+    assert_eq!(Some(&Value::Prim(PrimData::U8(0x7F))), fields.get("a"));
+    assert_eq!(Some(&Value::Prim(PrimData::U32(0x04030201))), fields.get("b"));
+
+    let written = section.write(&value, &layout).unwrap();
+    assert_eq!(bytes, written);
+}
+
+#[test]
+fn test_read_bit_fields_packed_into_one_byte() {
+    let layout = TargetDataLayout::default();
+    let structure = Struct {
+        name: "Flags".to_string(),
+        fields: vec![test_bits("a", 3), test_bits("b", 5)],
+        packing: None,
+    };
+    let section = Section::Struct(Box::new(structure));
+    // a = 0b101 (5), b = 0b00011 (3) -> byte = 0b00011_101 = 0x1D
+    let bytes = vec![0b0001_1101u8];
+
+    let value = section.read(&bytes, &layout).unwrap();
+    let Value::Struct(fields) = &value else { panic!("expected a struct") };
+    assert_eq!(Some(&Value::Prim(PrimData::U8(0b101))), fields.get("a"));
+    assert_eq!(Some(&Value::Prim(PrimData::U8(0b00011))), fields.get("b"));
+
+    assert_eq!(bytes, section.write(&value, &layout).unwrap());
+}
+
+#[test]
+fn test_read_short_buffer_is_an_error() {
+    let layout = TargetDataLayout::default();
+    let section = test_prim_int("n", IntWidth::Int32);
+    assert_eq!(
+        Err(Error::ShortBuffer { needed_bytes: 4, available_bytes: 2 }),
+        section.read(&[0, 0], &layout)
+    );
+}
+
+#[test]
+fn test_read_bit_field_wider_than_64_bits_is_an_error() {
+    let layout = TargetDataLayout::default();
+    let section = test_bits("n", 100);
+    assert_eq!(
+        Err(Error::UnsupportedBitWidth { width: 100 }),
+        section.read(&[0u8; 16], &layout)
+    );
+}
+
+#[test]
+fn test_read_union_keeps_raw_overlapping_bytes() {
+    let layout = TargetDataLayout::default();
+    let union = Union {
+        name: "U".to_string(),
+        fields: vec![test_prim_int("n", IntWidth::Int32), test_prim_int("b", IntWidth::Int8)],
+    };
+    let section = Section::Union(union);
+    let bytes = vec![0x01, 0x02, 0x03, 0x04];
+
+    let value = section.read(&bytes, &layout).unwrap();
+    assert_eq!(Value::Union(bytes.clone()), value);
+    assert_eq!(bytes, section.write(&value, &layout).unwrap());
+}
+
+#[test]
+fn test_optimized_order_sorts_by_descending_alignment() {
+    let layout = TargetDataLayout::default();
+    let structure = Struct {
+        name: "S".to_string(),
+        fields: vec![
+            test_prim_int("a", IntWidth::Int8),
+            test_prim_int("b", IntWidth::Int32),
+            test_prim_int("c", IntWidth::Int8),
+        ],
+        packing: None,
+    };
+    // declaration order: a(1), pad(3), b(4), c(1) -> 9 bytes, then padded out
+    // to a multiple of the struct's alignment (the max over its fields, 4): 12.
+    let section = Section::Struct(Box::new(structure));
+    assert_eq!(12, section.sizeof(&layout));
+
+    let Section::Struct(structure) = &section else { unreachable!() };
+    // Reordered by descending alignment: b(4), a(1), c(1) -> 4 + 1 + 1 = 6,
+    // then padded out to a multiple of the struct's new 4-byte alignment: 8.
+    let (optimized_size, order) = structure.sizeof_optimized(&layout);
+    assert_eq!(vec![1, 0, 2], order);
+    assert_eq!(8, optimized_size);
+}
+
+#[test]
+fn test_optimized_order_leaves_packed_structs_alone() {
+    let layout = TargetDataLayout::default();
+    let structure = Struct {
+        name: "S".to_string(),
+        fields: vec![
+            test_prim_int("a", IntWidth::Int8),
+            test_prim_int("b", IntWidth::Int32),
+        ],
+        packing: Some(1),
+    };
+    assert_eq!(vec![0, 1], structure.optimized_order(&layout));
+}
+
+#[test]
+fn test_optimized_order_leaves_bit_fields_alone() {
+    let layout = TargetDataLayout::default();
+    let structure = Struct {
+        name: "S".to_string(),
+        fields: vec![
+            test_prim_int("x", IntWidth::Int8),
+            test_bits("y", 20),
+            test_bits("z", 3),
+            test_prim_int("w", IntWidth::Int32),
+            test_bits("v", 3),
+        ],
+        packing: None,
+    };
+    // Reordering by pseudo-alignment would merge `z`'s run with `v`'s,
+    // silently splitting a bit field run that was adjacent in the
+    // declaration. A struct with any bit field keeps declaration order.
+    assert_eq!(vec![0, 1, 2, 3, 4], structure.optimized_order(&layout));
+}
+
+#[test]
+fn test_aggregate_align_applies_to_struct_and_union_sizeof() {
+    let layout = TargetDataLayout {
+        aggregate_align: AlignSpec { abi_bits: 128, pref_bits: 128 },
+        ..TargetDataLayout::default()
+    };
+
+    let structure = Struct {
+        name: "S".to_string(),
+        fields: vec![test_prim_int("a", IntWidth::Int8), test_prim_int("b", IntWidth::Int8)],
+        packing: None,
+    };
+    let section = Section::Struct(Box::new(structure));
+    assert_eq!(16, section.align_bytes(&layout));
+    assert_eq!(16, section.sizeof(&layout));
+
+    let union = Union {
+        name: "U".to_string(),
+        fields: vec![test_prim_int("a", IntWidth::Int8)],
+    };
+    let union_section = Section::Union(union);
+    assert_eq!(16, union_section.align_bytes(&layout));
+    assert_eq!(16, union_section.sizeof(&layout));
+
+    // Each array element's stride must be a multiple of its own alignment.
+    let array = Section::Array(Box::new(section), 3);
+    assert_eq!(48, array.sizeof(&layout));
+}